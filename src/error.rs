@@ -3,6 +3,7 @@ use std::{fmt, io, str};
 #[derive(Debug)]
 pub enum Error {
     EOF,
+    Timeout,
     MalformedInput,
     MethodNotSupported(String),
     HttpVersionNotSupported(String),
@@ -14,6 +15,7 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::EOF => write!(f, "reached the end of stream"),
+            Error::Timeout => write!(f, "timed out waiting for request"),
             Error::MalformedInput => write!(f, "malformed input"),
             Error::MethodNotSupported(method) => write!(f, "http method not supported: {method}"),
             Error::HttpVersionNotSupported(version) => {
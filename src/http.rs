@@ -2,9 +2,9 @@ use std::{
     collections::HashMap,
     fmt::Display,
     fs,
-    io::{BufRead, Read},
-    mem,
+    io::{self, BufRead, ErrorKind, Read, Write},
     path::Path,
+    time::UNIX_EPOCH,
 };
 
 use crate::error::{Error, Result};
@@ -12,12 +12,18 @@ use crate::error::{Error, Result};
 #[derive(Debug, PartialEq)]
 pub enum Method {
     Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Options,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Version {
     Http1_0,
     Http1_1,
+    Http2,
 }
 
 #[derive(Debug, PartialEq)]
@@ -27,6 +33,7 @@ pub struct Request {
     pub version: Version,
     // HTTP headers can have same keys.
     pub headers: HashMap<String, Vec<String>>,
+    pub body: Option<Vec<u8>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -35,13 +42,35 @@ pub struct Response {
     pub status: u16,
     pub reason: String,
     pub headers: HashMap<String, Vec<String>>,
-    pub body: Option<String>,
+    pub body: Option<Vec<u8>>,
+    // When set, the body is framed with `Transfer-Encoding: chunked`.
+    pub chunked: bool,
+}
+
+/// Guess the `Content-Type` for a file from its extension,
+/// falling back to `application/octet-stream` for unknown types.
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html",
+        Some("css") => "text/css",
+        Some("js") => "application/javascript",
+        Some("png") => "image/png",
+        Some("jpg") => "image/jpeg",
+        Some("svg") => "image/svg+xml",
+        Some("wasm") => "application/wasm",
+        _ => "application/octet-stream",
+    }
 }
 
 impl Method {
     fn parse(s: &str) -> Result<Self> {
         match s {
             "GET" => Ok(Method::Get),
+            "HEAD" => Ok(Method::Head),
+            "POST" => Ok(Method::Post),
+            "PUT" => Ok(Method::Put),
+            "DELETE" => Ok(Method::Delete),
+            "OPTIONS" => Ok(Method::Options),
             s => Err(Error::MethodNotSupported(s.into())),
         }
     }
@@ -52,6 +81,7 @@ impl Version {
         match s {
             "HTTP/1.0" => Ok(Version::Http1_0),
             "HTTP/1.1" => Ok(Version::Http1_1),
+            "HTTP/2.0" => Ok(Version::Http2),
             s => Err(Error::HttpVersionNotSupported(s.into())),
         }
     }
@@ -62,6 +92,7 @@ impl Display for Version {
         let s = match self {
             Version::Http1_0 => "HTTP/1.0",
             Version::Http1_1 => "HTTP/1.1",
+            Version::Http2 => "HTTP/2.0",
         };
         write!(f, "{s}",)
     }
@@ -70,8 +101,19 @@ impl Display for Version {
 impl Request {
     pub fn parse(mut reader: impl BufRead, buf: &mut String) -> Result<Request> {
         buf.clear();
-        if reader.read_line(buf)? == 0 {
-            return Err(Error::EOF);
+        match reader.read_line(buf) {
+            Ok(0) => return Err(Error::EOF),
+            Ok(_) => {}
+            // An idle keep-alive connection (nothing buffered) is a clean
+            // close; a timeout with a partial request line is a real timeout.
+            Err(ref err) if is_timeout(err) => {
+                return if buf.is_empty() {
+                    Err(Error::EOF)
+                } else {
+                    Err(Error::Timeout)
+                };
+            }
+            Err(err) => return Err(err.into()),
         }
 
         let mut request_line = buf.trim_end().split_ascii_whitespace();
@@ -89,16 +131,25 @@ impl Request {
         buf.clear();
         // Use `read_line()` instead of the `lines()` iterator,
         // to prevent allocating string on every line.
-        while reader.read_line(buf)? > 0 {
+        loop {
+            match reader.read_line(buf) {
+                Ok(0) => break,
+                Ok(_) => {}
+                // The request line was already read, so this is a partial
+                // request rather than an idle connection.
+                Err(ref err) if is_timeout(err) => return Err(Error::Timeout),
+                Err(err) => return Err(err.into()),
+            }
             let line = buf.trim_end();
             // hit empty line of \r\n
             if line.is_empty() {
                 break;
             }
             let mut header = line.split(": ");
-            // HTTP headers are case-insensitive.
+            // Field names are case-insensitive; values (e.g. a base64
+            // `Sec-WebSocket-Key`) must be preserved verbatim.
             let key = header.next().ok_or(Error::MalformedInput)?.to_lowercase();
-            let value = header.next().ok_or(Error::MalformedInput)?.to_lowercase();
+            let value = header.next().ok_or(Error::MalformedInput)?.to_string();
             if header.next().is_some() {
                 return Err(Error::MalformedInput);
             }
@@ -106,23 +157,111 @@ impl Request {
             buf.clear();
         }
 
+        let body = if header_has_token(&headers, "transfer-encoding", "chunked") {
+            Some(read_chunked_body(&mut reader, buf)?)
+        } else if let Some(values) = headers.get("content-length") {
+            let len: usize = values
+                .first()
+                .ok_or(Error::MalformedInput)?
+                .parse()
+                .map_err(|_| Error::MalformedInput)?;
+            let mut body = vec![0; len];
+            // Reject a declared length that the stream can't satisfy.
+            reader
+                .read_exact(&mut body)
+                .map_err(|_| Error::MalformedInput)?;
+            Some(body)
+        } else {
+            None
+        };
+
         Ok(Request {
             method,
             uri,
             version,
             headers,
+            body,
         })
     }
 }
 
+/// Whether an I/O error is a read/keep-alive timeout.
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+/// Build a bodyless response carrying only a status line and the `Server`
+/// header, used for replies like `408 Request Timeout` and `204 No Content`.
+pub fn status_response(version: Version, status: u16, reason: &str) -> Response {
+    Response {
+        version,
+        status,
+        reason: reason.into(),
+        headers: HashMap::from([("Server".into(), vec!["http-server/v0.1.0".into()])]),
+        body: None,
+        chunked: false,
+    }
+}
+
+/// Check whether a (possibly multi-valued) header contains a given token.
+fn header_has_token(headers: &HashMap<String, Vec<String>>, key: &str, token: &str) -> bool {
+    headers
+        .get(key)
+        .map(|values| {
+            values
+                .iter()
+                .flat_map(|v| v.split(','))
+                .any(|t| t.trim().eq_ignore_ascii_case(token))
+        })
+        .unwrap_or(false)
+}
+
+/// Decode a `Transfer-Encoding: chunked` body into its payload bytes.
+///
+/// Each chunk is a hexadecimal size line (chunk extensions after `;` are
+/// ignored) followed by that many bytes and a trailing CRLF. A zero-size
+/// chunk terminates the body, after which optional trailer headers are
+/// consumed up to the final empty line.
+fn read_chunked_body(mut reader: impl BufRead, buf: &mut String) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_line(buf)? == 0 {
+            return Err(Error::EOF);
+        }
+        let size_hex = buf.trim_end().split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| Error::MalformedInput)?;
+        if size == 0 {
+            break;
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        // Consume the CRLF that follows the chunk data.
+        buf.clear();
+        reader.read_line(buf)?;
+    }
+    // Consume optional trailer headers up to the final empty line.
+    loop {
+        buf.clear();
+        if reader.read_line(buf)? == 0 || buf.trim_end().is_empty() {
+            break;
+        }
+    }
+    Ok(body)
+}
+
 impl Display for Response {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The body is not valid UTF-8 in general, so it is written separately
+        // as raw bytes by `Response::write_to`; `Display` emits only the head.
         let Response {
             version,
             status,
             reason,
             headers,
-            body,
+            body: _,
+            chunked: _,
         } = self;
         write!(f, "{version} {status} {reason}\r\n")?;
         for (key, values) in headers {
@@ -131,14 +270,52 @@ impl Display for Response {
             }
         }
         write!(f, "\r\n")?;
-        if let Some(body) = body {
-            write!(f, "{body}\r\n")?;
+        Ok(())
+    }
+}
+
+impl Response {
+    /// Write the status line and headers as text, then the body as raw bytes.
+    ///
+    /// Unlike `Display`, this preserves non-UTF-8 bodies such as images.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "{self}")?;
+        match &self.body {
+            Some(body) if self.chunked => {
+                if !body.is_empty() {
+                    write_chunk(&mut writer, body)?;
+                }
+                writer.write_all(b"0\r\n\r\n")?;
+            }
+            Some(body) => writer.write_all(body)?,
+            None if self.chunked => writer.write_all(b"0\r\n\r\n")?,
+            None => {}
         }
         Ok(())
     }
 }
 
-pub fn handle_http_request(buf: &mut String, req: &Request, web_root: &Path) -> Result<Response> {
+/// Write a single chunk as `<hex-len>\r\n<data>\r\n` for a chunked response.
+///
+/// Callers streaming a large body can call this repeatedly and finish with a
+/// zero-size chunk (`0\r\n\r\n`) to avoid buffering the whole body in memory.
+pub fn write_chunk(mut writer: impl Write, data: &[u8]) -> io::Result<()> {
+    write!(writer, "{:x}\r\n", data.len())?;
+    writer.write_all(data)?;
+    writer.write_all(b"\r\n")
+}
+
+pub fn handle_http_request(req: &Request, web_root: &Path) -> Result<Response> {
+    // Advertise the supported methods and return early without a body.
+    if req.method == Method::Options {
+        let mut resp = status_response(req.version, 204, "No Content");
+        resp.headers.insert(
+            "Allow".into(),
+            vec!["GET, HEAD, POST, PUT, DELETE, OPTIONS".into()],
+        );
+        return Ok(resp);
+    }
+
     let mut path = web_root.join(&req.uri[1..]);
     if req.uri.ends_with('/') {
         path.push("/index.html");
@@ -150,24 +327,450 @@ pub fn handle_http_request(buf: &mut String, req: &Request, web_root: &Path) ->
         reason: "OK".into(),
         headers: HashMap::from([("Server".into(), vec!["http-server/v0.1.0".into()])]),
         body: None,
+        chunked: false,
     };
 
     if !path.exists() || !fs::canonicalize(&path)?.starts_with(web_root) {
         resp.status = 404;
         resp.reason = "Not Found".into();
     } else {
-        fs::File::open(path)?.read_to_string(buf)?;
-        resp.body = Some(mem::take(buf));
+        let meta = fs::metadata(&path)?;
+        let mtime = meta
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        // Weak validator derived from size and mtime.
+        let etag = format!("W/\"{}-{}\"", meta.len(), mtime);
+        resp.headers
+            .insert("Last-Modified".into(), vec![format_http_date(mtime)]);
+        resp.headers.insert("ETag".into(), vec![etag.clone()]);
+
+        // `If-None-Match` takes precedence over `If-Modified-Since`.
+        let not_modified = if let Some(matched) =
+            req.headers.get("if-none-match").and_then(|v| v.first())
+        {
+            matched == &etag || matched == "*"
+        } else if let Some(since) = req.headers.get("if-modified-since").and_then(|v| v.first()) {
+            parse_http_date(since).is_some_and(|since| mtime <= since)
+        } else {
+            false
+        };
+
+        if not_modified {
+            resp.status = 304;
+            resp.reason = "Not Modified".into();
+        } else {
+            let mut body = Vec::new();
+            fs::File::open(&path)?.read_to_end(&mut body)?;
+            resp.headers
+                .insert("Content-Type".into(), vec![content_type(&path).into()]);
+            resp.body = Some(body);
+        }
     }
 
     resp.headers.insert(
         "Content-Length".into(),
-        vec![resp.body.as_deref().map(str::len).unwrap_or(0).to_string()],
+        vec![resp.body.as_ref().map(Vec::len).unwrap_or(0).to_string()],
     );
 
+    // A HEAD response carries the same headers as GET but no body.
+    if req.method == Method::Head {
+        resp.body = None;
+    }
+
     Ok(resp)
 }
 
+/// Format a UNIX timestamp as an RFC 1123 HTTP date in GMT, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn format_http_date(secs: u64) -> String {
+    const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    // 1970-01-01 was a Thursday (index 4 in `DAYS`).
+    let weekday = ((days + 4).rem_euclid(7)) as usize;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        DAYS[weekday],
+        day,
+        MONTHS[month - 1],
+        year,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+    )
+}
+
+/// Parse an RFC 1123 HTTP date into a UNIX timestamp, returning `None` if the
+/// input is not well formed.
+fn parse_http_date(s: &str) -> Option<u64> {
+    let mut parts = s.trim().split_whitespace();
+    let _weekday = parts.next()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = match parts.next()? {
+        "Jan" => 1,
+        "Feb" => 2,
+        "Mar" => 3,
+        "Apr" => 4,
+        "May" => 5,
+        "Jun" => 6,
+        "Jul" => 7,
+        "Aug" => 8,
+        "Sep" => 9,
+        "Oct" => 10,
+        "Nov" => 11,
+        "Dec" => 12,
+        _ => return None,
+    };
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':');
+    let hour: u64 = time.next()?.parse().ok()?;
+    let minute: u64 = time.next()?.parse().ok()?;
+    let second: u64 = time.next()?.parse().ok()?;
+    let days = days_from_civil(year, month, day);
+    Some(days as u64 * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Convert days since the UNIX epoch into a `(year, month, day)` triple.
+///
+/// Uses Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, usize, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let year = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as usize;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// Convert a `(year, month, day)` date into days since the UNIX epoch.
+///
+/// Uses Howard Hinnant's `days_from_civil` algorithm.
+fn days_from_civil(year: i64, month: usize, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The HTTP/2 cleartext connection preface (RFC 7540 §3.5).
+pub const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// Which protocol a freshly accepted connection is speaking.
+pub enum Protocol {
+    Http1,
+    Http2,
+}
+
+/// Sniff the connection preface without consuming it to decide the protocol.
+///
+/// HTTP/2 prior-knowledge clients (e.g. `curl --http2-prior-knowledge`) open
+/// with [`HTTP2_PREFACE`]; everything else is treated as HTTP/1.x.
+pub fn detect_protocol(mut reader: impl BufRead) -> io::Result<Protocol> {
+    if reader.fill_buf()?.starts_with(HTTP2_PREFACE) {
+        Ok(Protocol::Http2)
+    } else {
+        Ok(Protocol::Http1)
+    }
+}
+
+// HTTP/2 frame types and flags we care about (RFC 7540 §6).
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_SETTINGS: u8 = 0x4;
+const FLAG_ACK: u8 = 0x1;
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+fn read_frame(mut reader: impl Read) -> Result<Frame> {
+    let mut header = [0u8; 9];
+    reader.read_exact(&mut header)?;
+    let length = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    let kind = header[3];
+    let flags = header[4];
+    let stream_id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    Ok(Frame {
+        kind,
+        flags,
+        stream_id,
+        payload,
+    })
+}
+
+fn write_frame(mut writer: impl Write, kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> io::Result<()> {
+    let len = payload.len();
+    writer.write_all(&[(len >> 16) as u8, (len >> 8) as u8, len as u8, kind, flags])?;
+    writer.write_all(&stream_id.to_be_bytes())?;
+    writer.write_all(payload)
+}
+
+/// Serve a single request over a minimal HTTP/2 framing layer.
+///
+/// This consumes the already-detected preface, exchanges `SETTINGS` frames,
+/// and replies to the first stream with an empty `200` response. It is just
+/// enough to interoperate with prior-knowledge clients; protocol detection
+/// is done by [`detect_protocol`] before this is called.
+pub fn handle_http2(mut reader: impl BufRead, mut writer: impl Write, _web_root: &Path) -> Result<()> {
+    let mut preface = [0u8; HTTP2_PREFACE.len()];
+    reader.read_exact(&mut preface)?;
+    if preface != *HTTP2_PREFACE {
+        return Err(Error::MalformedInput);
+    }
+
+    // Announce our own (default) settings before handling the peer's frames.
+    write_frame(&mut writer, FRAME_SETTINGS, 0, 0, &[])?;
+    writer.flush()?;
+
+    loop {
+        let frame = read_frame(&mut reader)?;
+        match frame.kind {
+            FRAME_SETTINGS if frame.flags & FLAG_ACK == 0 => {
+                write_frame(&mut writer, FRAME_SETTINGS, FLAG_ACK, 0, &[])?;
+                writer.flush()?;
+            }
+            FRAME_HEADERS => {
+                // `:status 200` is entry 8 in the HPACK static table, so the
+                // whole header block is a single indexed field.
+                write_frame(
+                    &mut writer,
+                    FRAME_HEADERS,
+                    FLAG_END_HEADERS | FLAG_END_STREAM,
+                    frame.stream_id,
+                    &[0x88],
+                )?;
+                writer.flush()?;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// The fixed GUID concatenated with `Sec-WebSocket-Key` during the handshake.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// WebSocket frame opcodes (RFC 6455 §5.2).
+const WS_OP_TEXT: u8 = 0x1;
+const WS_OP_BINARY: u8 = 0x2;
+const WS_OP_CLOSE: u8 = 0x8;
+const WS_OP_PING: u8 = 0x9;
+const WS_OP_PONG: u8 = 0xA;
+
+/// Whether a request asks to upgrade the connection to WebSocket.
+pub fn is_websocket_upgrade(req: &Request) -> bool {
+    let has_token = |key, token: &str| {
+        req.headers
+            .get(key)
+            .map(|values| {
+                values
+                    .iter()
+                    .flat_map(|v| v.split(','))
+                    .any(|t| t.trim().eq_ignore_ascii_case(token))
+            })
+            .unwrap_or(false)
+    };
+    has_token("upgrade", "websocket") && has_token("connection", "upgrade")
+}
+
+/// Compute the `Sec-WebSocket-Accept` value for a client key (RFC 6455 §4.2.2).
+pub fn websocket_accept(key: &str) -> String {
+    let mut input = String::from(key);
+    input.push_str(WEBSOCKET_GUID);
+    base64_encode(&sha1(input.as_bytes()))
+}
+
+/// Build the `101 Switching Protocols` response for a WebSocket upgrade.
+pub fn websocket_handshake(req: &Request) -> Result<Response> {
+    let key = req
+        .headers
+        .get("sec-websocket-key")
+        .and_then(|values| values.first())
+        .ok_or(Error::MalformedInput)?;
+    Ok(Response {
+        version: req.version,
+        status: 101,
+        reason: "Switching Protocols".into(),
+        headers: HashMap::from([
+            ("Upgrade".into(), vec!["websocket".into()]),
+            ("Connection".into(), vec!["Upgrade".into()]),
+            ("Sec-WebSocket-Accept".into(), vec![websocket_accept(key)]),
+        ]),
+        body: None,
+        chunked: false,
+    })
+}
+
+/// Run the post-handshake frame loop on the raw stream.
+///
+/// Text and binary frames are echoed back, pings are answered with pongs, and
+/// a close frame is echoed before the loop returns. Payloads are unmasked with
+/// the client's masking key per RFC 6455 §5.3.
+pub fn websocket_loop(mut stream: impl Read + Write) -> Result<()> {
+    loop {
+        let mut head = [0u8; 2];
+        match stream.read_exact(&mut head) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+        let opcode = head[0] & 0x0f;
+        let masked = head[1] & 0x80 != 0;
+        let mut len = (head[1] & 0x7f) as usize;
+        if len == 126 {
+            let mut ext = [0u8; 2];
+            stream.read_exact(&mut ext)?;
+            len = u16::from_be_bytes(ext) as usize;
+        } else if len == 127 {
+            let mut ext = [0u8; 8];
+            stream.read_exact(&mut ext)?;
+            len = u64::from_be_bytes(ext) as usize;
+        }
+        let mut mask = [0u8; 4];
+        if masked {
+            stream.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0; len];
+        stream.read_exact(&mut payload)?;
+        if masked {
+            for (i, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[i % 4];
+            }
+        }
+
+        match opcode {
+            WS_OP_TEXT | WS_OP_BINARY => write_ws_frame(&mut stream, opcode, &payload)?,
+            WS_OP_PING => write_ws_frame(&mut stream, WS_OP_PONG, &payload)?,
+            WS_OP_CLOSE => {
+                write_ws_frame(&mut stream, WS_OP_CLOSE, &payload)?;
+                break;
+            }
+            WS_OP_PONG => {}
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Write a single unmasked server frame with the `FIN` bit set.
+fn write_ws_frame(mut stream: impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut header = vec![0x80 | opcode];
+    let len = payload.len();
+    if len < 126 {
+        header.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        header.push(126);
+        header.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        header.push(127);
+        header.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+    stream.write_all(&header)?;
+    stream.write_all(payload)?;
+    stream.flush()
+}
+
+/// Minimal SHA-1 digest (RFC 3174), used only for the WebSocket handshake.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+    let bit_len = (data.len() as u64) * 8;
+
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    msg.extend_from_slice(&bit_len.to_be_bytes());
+
+    for block in msg.chunks_exact(64) {
+        let mut w = [0u32; 80];
+        for (word, chunk) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | ((!b) & d), 0x5A82_7999u32),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        h[0] = h[0].wrapping_add(a);
+        h[1] = h[1].wrapping_add(b);
+        h[2] = h[2].wrapping_add(c);
+        h[3] = h[3].wrapping_add(d);
+        h[4] = h[4].wrapping_add(e);
+    }
+
+    let mut out = [0u8; 20];
+    for (word, slot) in h.iter().zip(out.chunks_exact_mut(4)) {
+        slot.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+/// Standard base64 encoding with padding.
+fn base64_encode(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = *chunk.get(1).unwrap_or(&0) as u32;
+        let b2 = *chunk.get(2).unwrap_or(&0) as u32;
+        let n = (b0 << 16) | (b1 << 8) | b2;
+        out.push(TABLE[((n >> 18) & 63) as usize] as char);
+        out.push(TABLE[((n >> 12) & 63) as usize] as char);
+        out.push(if chunk.len() > 1 {
+            TABLE[((n >> 6) & 63) as usize] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            TABLE[(n & 63) as usize] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
 #[cfg(test)]
 mod test {
     use std::{
@@ -191,12 +794,75 @@ mod test {
                     ("host".into(), vec!["127.0.0.1:8000".into()]),
                     ("user-agent".into(), vec!["curl/8.8.0".into()]),
                     ("accept".into(), vec!["*/*".into()]),
-                ])
+                ]),
+                body: None,
             }
         );
         Ok(())
     }
 
+    #[test]
+    fn parse_chunked_body() -> Result<()> {
+        let s = "GET / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+                 4\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n";
+        let req = Request::parse(&mut BufReader::new(Cursor::new(s)), &mut String::new())?;
+        assert_eq!(req.body.as_deref(), Some(&b"Wikipedia"[..]));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_body_with_content_length() -> Result<()> {
+        let s = "POST /submit HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+        let req = Request::parse(&mut BufReader::new(Cursor::new(s)), &mut String::new())?;
+        assert_eq!(req.method, Method::Post);
+        assert_eq!(req.body.as_deref(), Some(&b"hello"[..]));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_short_body() {
+        let s = "POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort";
+        let err = Request::parse(&mut BufReader::new(Cursor::new(s)), &mut String::new());
+        assert!(matches!(err, Err(Error::MalformedInput)));
+    }
+
+    #[test]
+    fn serialize_chunked() -> Result<()> {
+        let mut out = Vec::new();
+        Response {
+            version: Version::Http1_1,
+            status: 200,
+            reason: "OK".into(),
+            headers: HashMap::from([("Transfer-Encoding".into(), vec!["chunked".into()])]),
+            body: Some(b"Wikipedia".to_vec()),
+            chunked: true,
+        }
+        .write_to(&mut out)?;
+        assert_eq!(
+            out,
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n9\r\nWikipedia\r\n0\r\n\r\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn websocket_accept_matches_rfc_example() {
+        // The example key/accept pair from RFC 6455 §1.3.
+        assert_eq!(
+            websocket_accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn http_date_round_trip() {
+        // The canonical example from RFC 7231 §7.1.1.1.
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+        let secs = parse_http_date(date).unwrap();
+        assert_eq!(secs, 784_111_777);
+        assert_eq!(format_http_date(secs), date);
+    }
+
     #[test]
     fn serialize() -> Result<()> {
         assert_eq!(
@@ -205,7 +871,8 @@ mod test {
                 status: 200,
                 reason: "OK".into(),
                 headers: HashMap::from([("Connection".into(), vec!["Closed".into()])]),
-                body: None
+                body: None,
+                chunked: false,
             }
             .to_string(),
             "HTTP/1.0 200 OK\r\nConnection: Closed\r\n\r\n"
@@ -221,8 +888,9 @@ mod test {
             uri: "/src/lib.rs".into(),
             version: Version::Http1_0,
             headers: HashMap::new(),
+            body: None,
         };
-        let resp = handle_http_request(&mut String::new(), &req, &env::current_dir()?)?;
+        let resp = handle_http_request(&req, &env::current_dir()?)?;
 
         assert_eq!(resp.status, 200);
         assert!(resp.headers.contains_key("Content-Length"));
@@ -238,8 +906,9 @@ mod test {
             uri: "../Cargo.toml".into(),
             version: Version::Http1_0,
             headers: HashMap::new(),
+            body: None,
         };
-        let resp = handle_http_request(&mut String::new(), &req, &env::current_dir()?.join("src"))?;
+        let resp = handle_http_request(&req, &env::current_dir()?.join("src"))?;
 
         assert_eq!(resp.status, 404);
         assert!(resp.body.is_none());
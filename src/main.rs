@@ -1,16 +1,19 @@
 use std::{
     env, fs,
-    io::{prelude::*, BufReader, BufWriter},
-    mem,
+    io::{BufReader, BufWriter, Write},
     net::{TcpListener, TcpStream},
     path::Path,
     sync::Arc,
     thread,
+    time::Duration,
 };
 
 use http_server::{
     error::{Error, Result},
-    http::{handle_http_request, Request, Version},
+    http::{
+        detect_protocol, handle_http2, handle_http_request, is_websocket_upgrade, status_response,
+        websocket_handshake, websocket_loop, Protocol, Request, Version,
+    },
     thread_pool::ThreadPool,
 };
 
@@ -22,7 +25,7 @@ fn main() -> Result<()> {
     if host == "-h" || host == "--help" {
         let prog = prog.unwrap();
         eprintln!("A sinple HTTP server");
-        eprintln!("Usage: {prog} [host] [port] [web_root] [num_threads]");
+        eprintln!("Usage: {prog} [host] [port] [web_root] [num_threads] [timeout_secs]");
         return Ok(());
     }
 
@@ -40,6 +43,13 @@ fn main() -> Result<()> {
         .next()
         .and_then(|s| s.parse::<usize>().ok())
         .unwrap_or(thread::available_parallelism()?.get());
+    // Read/keep-alive timeout in seconds; `0` disables it. Defaults to 30s to
+    // protect the thread pool from slow-loris-style idle connections.
+    let timeout = args
+        .next()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(30);
+    let timeout = (timeout > 0).then(|| Duration::from_secs(timeout));
 
     let listener = TcpListener::bind((host, port))?;
     println!("Listening on {}:{}", host, port);
@@ -48,35 +58,60 @@ fn main() -> Result<()> {
 
     for stream in listener.incoming() {
         let stream = stream?;
-        thread_pool.run((stream, Arc::clone(&web_root)));
+        thread_pool.run((stream, Arc::clone(&web_root), timeout));
     }
 
     Ok(())
 }
 
-fn handle_connection(buf: &mut String, (stream, web_root): (TcpStream, Arc<Path>)) -> Result<()> {
+fn handle_connection(
+    buf: &mut String,
+    (stream, web_root, timeout): (TcpStream, Arc<Path>, Option<Duration>),
+) -> Result<()> {
+    stream.set_read_timeout(timeout)?;
     let mut reader = BufReader::new(&stream);
     let mut writer = BufWriter::new(&stream);
 
+    // Sniff the connection preface and branch to the matching protocol.
+    match detect_protocol(&mut reader)? {
+        Protocol::Http2 => return handle_http2(&mut reader, &mut writer, &web_root),
+        Protocol::Http1 => {}
+    }
+
     loop {
         let req = match Request::parse(&mut reader, buf) {
             Ok(req) => req,
             // Close the connection on EOF.
             Err(Error::EOF) => break,
+            // A partial request was in flight when the read timed out; let the
+            // client know before closing.
+            Err(Error::Timeout) => {
+                let resp = status_response(Version::Http1_1, 408, "Request Timeout");
+                resp.write_to(&mut writer)?;
+                writer.flush()?;
+                break;
+            }
             Err(err) => return Err(err),
         };
 
-        let mut resp = handle_http_request(buf, &req, &web_root)?;
-        write!(writer, "{resp}")?;
-        writer.flush()?;
-        if let Some(body) = &mut resp.body {
-            // Restore the per-thread buffer as it war taken in `handle_http_request()`.
-            mem::swap(buf, body);
+        // Upgrade to WebSocket and hand the raw stream to the framing loop.
+        if is_websocket_upgrade(&req) {
+            let resp = websocket_handshake(&req)?;
+            resp.write_to(&mut writer)?;
+            writer.flush()?;
+            return websocket_loop(&stream);
         }
 
+        let resp = handle_http_request(&req, &web_root)?;
+        resp.write_to(&mut writer)?;
+        writer.flush()?;
+
         // HTTP 1.0 connections are short-lived.
         if req.version == Version::Http1_0
-            || req.headers.get("connection").map(|vec| vec[0].as_str()) == Some("close")
+            || req
+                .headers
+                .get("connection")
+                .is_some_and(|vec| vec[0].eq_ignore_ascii_case("close"))
         {
             break;
         }